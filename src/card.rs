@@ -1,180 +1,247 @@
-use itertools::Itertools;
-
-use crate::errors::*;
-
-#[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
-pub enum CardValue {
-    VA,
-    V2,
-    V3,
-    V4,
-    V5,
-    V6,
-    V7,
-    V8,
-    V9,
-    VT,
-    VJ,
-    VQ,
-    VK,
-}
-
-#[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
-pub enum CardSuit {
-    Clubs,
-    Hearts,
-    Spades,
-    Diamonds,
-}
-
-#[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
-pub struct Card {
-    suit: CardSuit,
-    val: CardValue,
-}
-
-impl Card {
-    pub fn new(val: CardValue, suit: CardSuit) -> Card {
-        Card { suit, val }
-    }
-
-    pub fn parse_vec(s: &str) -> Result<Vec<Card>> {
-        Card::parse_vec_pat(s, ",")
-    }
-
-    pub fn parse_vec_pat(s: &str, pat: &str) -> Result<Vec<Card>> {
-        s.split(pat)
-            .map(|piece| piece.trim())
-            .map(|trimmed| Card::parse(trimmed))
-            .fold_results(Vec::new(), |mut vec, crd| {vec.push(crd.0); vec })
-    }
-
-    pub fn parse(s: &str) -> Result<(Card, &str)> {
-        // Grammar.
-        // Note: no white space is allowed before, after, or inside the card string.
-        //
-        // Value: 'A' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | 'T' | 'J' | 'Q' | 'K' | '10'
-        // Suit: 'C' | 'H' | 'S' | 'D'
-        // Card: Value+Suit
-        let (val, rest) = Card::read_value(&s)?;
-        let (suit, rest) = Card::read_suit(rest)?;
-
-        Ok((Card::new(val, suit), rest))
-    }
-
-    fn read_value(s: &str) -> Result<(CardValue, &str)> {
-        let mut start = 1;
-        let mut chars = s.chars();
-        let value = match chars.next() {
-            Some('A') => Option::Some(CardValue::VA),
-            Some('2') => Option::Some(CardValue::V2),
-            Some('3') => Option::Some(CardValue::V3),
-            Some('4') => Option::Some(CardValue::V4),
-            Some('5') => Option::Some(CardValue::V5),
-            Some('6') => Option::Some(CardValue::V6),
-            Some('7') => Option::Some(CardValue::V7),
-            Some('8') => Option::Some(CardValue::V8),
-            Some('9') => Option::Some(CardValue::V9),
-            Some('T') => Option::Some(CardValue::VT),
-            Some('J') => Option::Some(CardValue::VJ),
-            Some('Q') => Option::Some(CardValue::VQ),
-            Some('K') => Option::Some(CardValue::VK),
-            Some('1') => {
-                if chars.next() == Some('0') {
-                    start = 2;
-                    Option::Some(CardValue::VT)
-                } else {
-                    Option::None
-                }
-            }
-            _ => Option::None,
-        };
-
-        let tuple = value.map(|v| (v, &s[start..]));
-        tuple.ok_or_else(|| ErrorKind::UnrecognizedCardValue(s.to_owned()).into())
-    }
-
-    fn read_suit(str: &str) -> Result<(CardSuit, &str)> {
-        let suit = match str.chars().next() {
-            Some('S') => Option::Some(CardSuit::Spades),
-            Some('H') => Option::Some(CardSuit::Hearts),
-            Some('D') => Option::Some(CardSuit::Diamonds),
-            Some('C') => Option::Some(CardSuit::Clubs),
-            _ => Option::None,
-        };
-
-        let tuple = suit.map(|s| (s, &str[1..]));
-        tuple.ok_or_else(|| ErrorKind::UnrecognizedSuit(str.to_owned()).into())
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn test_ord() {
-        let ace_s = Card::new(CardValue::VA, CardSuit::Spades);
-        let two_s = Card::new(CardValue::V2, CardSuit::Spades);
-        assert!(ace_s < two_s);
-        assert_eq!(ace_s, ace_s);
-        assert_eq!(two_s, two_s);
-    }
-
-    #[test]
-    fn test_parse_suit() {
-        assert_eq!((CardSuit::Spades, "3"), Card::read_suit("S3").unwrap());
-        assert_eq!((CardSuit::Hearts, "4"), Card::read_suit("H4").unwrap());
-        assert_eq!((CardSuit::Diamonds, "5"), Card::read_suit("D5").unwrap());
-        assert_eq!((CardSuit::Clubs, "6"), Card::read_suit("C6").unwrap());
-        assert!(Card::read_suit("X").is_err());
-        assert!(Card::read_suit("").is_err());
-    }
-
-    #[test]
-    fn test_parse_value() {
-        assert_eq!((CardValue::VA, "X"), Card::read_value("AX").unwrap());
-        assert_eq!((CardValue::V2, "X"), Card::read_value("2X").unwrap());
-        assert_eq!((CardValue::V3, "X"), Card::read_value("3X").unwrap());
-        assert_eq!((CardValue::V4, "X"), Card::read_value("4X").unwrap());
-        assert_eq!((CardValue::V5, "X"), Card::read_value("5X").unwrap());
-        assert_eq!((CardValue::V6, "X"), Card::read_value("6X").unwrap());
-        assert_eq!((CardValue::V7, "X"), Card::read_value("7X").unwrap());
-        assert_eq!((CardValue::V8, "X"), Card::read_value("8X").unwrap());
-        assert_eq!((CardValue::V9, "X"), Card::read_value("9X").unwrap());
-        assert_eq!((CardValue::VT, "X"), Card::read_value("TX").unwrap());
-        assert_eq!((CardValue::VJ, "X"), Card::read_value("JX").unwrap());
-        assert_eq!((CardValue::VQ, "X"), Card::read_value("QX").unwrap());
-        assert_eq!((CardValue::VK, "X"), Card::read_value("KX").unwrap());
-
-        assert_eq!((CardValue::VT, "X"), Card::read_value("10X").unwrap());
-
-        assert!(Card::read_value("XX").is_err());
-        assert!(Card::read_value("11").is_err());
-    }
-
-    #[test]
-    fn test_parse() {
-        assert_eq!(
-            (Card::new(CardValue::VA, CardSuit::Spades), "REST"),
-            Card::parse("ASREST").unwrap()
-        );
-        assert_eq!(
-            (Card::new(CardValue::V2, CardSuit::Spades), "REST"),
-            Card::parse("2SREST").unwrap()
-        );
-        assert_eq!(
-            (Card::new(CardValue::VA, CardSuit::Hearts), "REST"),
-            Card::parse("AHREST").unwrap()
-        );
-        assert_eq!(
-            (Card::new(CardValue::V8, CardSuit::Clubs), "REST"),
-            Card::parse("8CREST").unwrap()
-        );
-
-        assert_eq!(
-            (Card::new(CardValue::VT, CardSuit::Spades), "REST"),
-            Card::parse("10SREST").unwrap()
-        );
-    }
-}
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::multispace0;
+use nom::combinator::map;
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair};
+use nom::IResult;
+
+use crate::errors::*;
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub enum CardValue {
+    VA,
+    V2,
+    V3,
+    V4,
+    V5,
+    V6,
+    V7,
+    V8,
+    V9,
+    VT,
+    VJ,
+    VQ,
+    VK,
+}
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub enum CardSuit {
+    Clubs,
+    Hearts,
+    Spades,
+    Diamonds,
+}
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub struct Card {
+    suit: CardSuit,
+    val: CardValue,
+}
+
+// Grammar.
+// Note: no white space is allowed before, after, or inside the card string.
+//
+// Value: 'A' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | 'T' | 'J' | 'Q' | 'K' | '10'
+// Suit: 'C' | 'H' | 'S' | 'D'
+// Card: Value+Suit
+//
+// "10" is listed first; no other alternative starts with '1', so the order of the tags does
+// not change which value is matched.
+pub(crate) fn value(s: &str) -> IResult<&str, CardValue> {
+    alt((
+        map(tag("10"), |_| CardValue::VT),
+        map(tag("A"), |_| CardValue::VA),
+        map(tag("2"), |_| CardValue::V2),
+        map(tag("3"), |_| CardValue::V3),
+        map(tag("4"), |_| CardValue::V4),
+        map(tag("5"), |_| CardValue::V5),
+        map(tag("6"), |_| CardValue::V6),
+        map(tag("7"), |_| CardValue::V7),
+        map(tag("8"), |_| CardValue::V8),
+        map(tag("9"), |_| CardValue::V9),
+        map(tag("T"), |_| CardValue::VT),
+        map(tag("J"), |_| CardValue::VJ),
+        map(tag("Q"), |_| CardValue::VQ),
+        map(tag("K"), |_| CardValue::VK),
+    ))(s)
+}
+
+pub(crate) fn suit(s: &str) -> IResult<&str, CardSuit> {
+    alt((
+        map(tag("S"), |_| CardSuit::Spades),
+        map(tag("H"), |_| CardSuit::Hearts),
+        map(tag("D"), |_| CardSuit::Diamonds),
+        map(tag("C"), |_| CardSuit::Clubs),
+    ))(s)
+}
+
+pub(crate) fn card(s: &str) -> IResult<&str, Card> {
+    let (rest, (val, suit)) = pair(value, suit)(s)?;
+    Ok((rest, Card::new(val, suit)))
+}
+
+// Nom reports failures as the slice where matching gave up, which is more useful than the
+// original full input. `Incomplete` has no such slice (it can't happen with `complete`
+// parsers over a non-streaming `&str`), so it falls back to `fallback`.
+fn failure_input<'a>(e: nom::Err<nom::error::Error<&'a str>>, fallback: &'a str) -> &'a str {
+    match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+        nom::Err::Incomplete(_) => fallback,
+    }
+}
+
+impl Card {
+    pub fn new(val: CardValue, suit: CardSuit) -> Card {
+        Card { suit, val }
+    }
+
+    pub fn parse_vec(s: &str) -> Result<Vec<Card>> {
+        Card::parse_vec_pat(s, ",")
+    }
+
+    pub fn parse_vec_pat(s: &str, pat: &str) -> Result<Vec<Card>> {
+        match separated_list1(tag(pat), delimited(multispace0, card, multispace0))(s) {
+            Ok((rest, cards)) if rest.is_empty() => Ok(cards),
+            Ok((rest, _cards)) => {
+                // `separated_list1` backtracks the whole `(separator, item)` pair when an
+                // item fails to parse, so `rest` still starts with the separator; strip it
+                // (and any leading whitespace) to land on the bad card, then re-parse it
+                // alone so the error carries the right `ErrorKind` and a tight failure slice.
+                let item = rest.strip_prefix(pat).unwrap_or(rest).trim_start();
+                Err(Card::leftover_error(item))
+            }
+            // Parsing the very first card failed outright (this also covers empty input,
+            // which `separated_list1` rejects, matching the "at least one card" contract).
+            Err(_) => Err(Card::leftover_error(s.trim_start())),
+        }
+    }
+
+    fn leftover_error(item: &str) -> Error {
+        match Card::parse(item) {
+            Err(e) => e,
+            Ok(_) => ErrorKind::UnrecognizedCardValue(item.to_owned()).into(),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<(Card, &str)> {
+        let (val, rest) = Card::read_value(s)?;
+        let (suit, rest) = Card::read_suit(rest)?;
+
+        Ok((Card::new(val, suit), rest))
+    }
+
+    fn read_value(s: &str) -> Result<(CardValue, &str)> {
+        value(s)
+            .map(|(rest, v)| (v, rest))
+            .map_err(|e| ErrorKind::UnrecognizedCardValue(failure_input(e, s).to_owned()).into())
+    }
+
+    fn read_suit(s: &str) -> Result<(CardSuit, &str)> {
+        suit(s)
+            .map(|(rest, v)| (v, rest))
+            .map_err(|e| ErrorKind::UnrecognizedSuit(failure_input(e, s).to_owned()).into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ord() {
+        let ace_s = Card::new(CardValue::VA, CardSuit::Spades);
+        let two_s = Card::new(CardValue::V2, CardSuit::Spades);
+        assert!(ace_s < two_s);
+        assert_eq!(ace_s, ace_s);
+        assert_eq!(two_s, two_s);
+    }
+
+    #[test]
+    fn test_parse_suit() {
+        assert_eq!((CardSuit::Spades, "3"), Card::read_suit("S3").unwrap());
+        assert_eq!((CardSuit::Hearts, "4"), Card::read_suit("H4").unwrap());
+        assert_eq!((CardSuit::Diamonds, "5"), Card::read_suit("D5").unwrap());
+        assert_eq!((CardSuit::Clubs, "6"), Card::read_suit("C6").unwrap());
+        assert!(Card::read_suit("X").is_err());
+        assert!(Card::read_suit("").is_err());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!((CardValue::VA, "X"), Card::read_value("AX").unwrap());
+        assert_eq!((CardValue::V2, "X"), Card::read_value("2X").unwrap());
+        assert_eq!((CardValue::V3, "X"), Card::read_value("3X").unwrap());
+        assert_eq!((CardValue::V4, "X"), Card::read_value("4X").unwrap());
+        assert_eq!((CardValue::V5, "X"), Card::read_value("5X").unwrap());
+        assert_eq!((CardValue::V6, "X"), Card::read_value("6X").unwrap());
+        assert_eq!((CardValue::V7, "X"), Card::read_value("7X").unwrap());
+        assert_eq!((CardValue::V8, "X"), Card::read_value("8X").unwrap());
+        assert_eq!((CardValue::V9, "X"), Card::read_value("9X").unwrap());
+        assert_eq!((CardValue::VT, "X"), Card::read_value("TX").unwrap());
+        assert_eq!((CardValue::VJ, "X"), Card::read_value("JX").unwrap());
+        assert_eq!((CardValue::VQ, "X"), Card::read_value("QX").unwrap());
+        assert_eq!((CardValue::VK, "X"), Card::read_value("KX").unwrap());
+
+        assert_eq!((CardValue::VT, "X"), Card::read_value("10X").unwrap());
+
+        assert!(Card::read_value("XX").is_err());
+        assert!(Card::read_value("11").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            (Card::new(CardValue::VA, CardSuit::Spades), "REST"),
+            Card::parse("ASREST").unwrap()
+        );
+        assert_eq!(
+            (Card::new(CardValue::V2, CardSuit::Spades), "REST"),
+            Card::parse("2SREST").unwrap()
+        );
+        assert_eq!(
+            (Card::new(CardValue::VA, CardSuit::Hearts), "REST"),
+            Card::parse("AHREST").unwrap()
+        );
+        assert_eq!(
+            (Card::new(CardValue::V8, CardSuit::Clubs), "REST"),
+            Card::parse("8CREST").unwrap()
+        );
+
+        assert_eq!(
+            (Card::new(CardValue::VT, CardSuit::Spades), "REST"),
+            Card::parse("10SREST").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_vec_whitespace() {
+        let cards = Card::parse_vec("AS, 2H , 10D").unwrap();
+        assert_eq!(
+            vec![
+                Card::new(CardValue::VA, CardSuit::Spades),
+                Card::new(CardValue::V2, CardSuit::Hearts),
+                Card::new(CardValue::VT, CardSuit::Diamonds),
+            ],
+            cards
+        );
+    }
+
+    #[test]
+    fn test_parse_vec_empty_is_error() {
+        assert!(Card::parse_vec("").is_err());
+    }
+
+    #[test]
+    fn test_parse_vec_bad_card_reports_precise_error() {
+        match Card::parse_vec("AX") {
+            Err(Error(ErrorKind::UnrecognizedSuit(s), _)) => assert_eq!("X", s),
+            other => panic!("expected UnrecognizedSuit(\"X\"), got {:?}", other),
+        }
+
+        match Card::parse_vec("AS,2X") {
+            Err(Error(ErrorKind::UnrecognizedSuit(s), _)) => assert_eq!("X", s),
+            other => panic!("expected UnrecognizedSuit(\"X\"), got {:?}", other),
+        }
+    }
+}